@@ -1,77 +1,1090 @@
 use crate::eth::types::*;
 use crate::types::*;
+use alloy_json_rpc::RpcError;
 use alloy_pubsub::{PubSubFrontend, RawSubscription};
 use alloy_rpc_client::ClientBuilder;
 use alloy_rpc_types::pubsub::SubscriptionResult;
+use alloy_transport::TransportErrorKind;
+use alloy_transport_http::Http;
 use alloy_transport_ws::WsConnect;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
 use tokio::task::JoinHandle;
 use url::Url;
 
+/// Default interval an HTTP-backed subscription polls the backend for new
+/// logs, since plain HTTP has no `eth_subscribe` push channel to piggyback
+/// on. Overridable per-endpoint via `RpcEndpoint::poll_interval`.
+const HTTP_POLL_INTERVAL: Duration = Duration::from_secs(4);
+/// How often the shared block watcher polls for a new head when it has no
+/// websocket backend to push `newHeads` notifications to it.
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(4);
+/// How many consecutive `eth_getLogs` poll failures a polled subscription
+/// tolerates before giving up on it and notifying the subscriber, rather than
+/// retrying a query range the backend may keep rejecting forever.
+const MAX_POLL_FAILURES: u32 = 5;
+/// JSON-RPC methods whose responses are safe to cache by `(method, params)`
+/// for a short while: idempotent, by-hash lookups that don't change once
+/// the referenced block/transaction is mined.
+const CACHEABLE_METHODS: &[&str] = &["eth_getBlockByHash", "eth_getTransactionReceipt"];
+/// Maximum number of `(method, params)` entries kept in the TTL cache before
+/// the oldest is evicted, so a long-running node doesn't grow this unbounded.
+const CACHE_CAPACITY: usize = 1024;
+/// Number of most-recent blocks the gas oracle keeps priority-fee samples
+/// for, mirroring the window `eth_feeHistory` is usually asked over.
+const GAS_ORACLE_WINDOW: usize = 20;
+/// Number of observed pending-transaction priority fees kept around to blend
+/// into the estimate between blocks.
+const PENDING_FEE_WINDOW: usize = 200;
+/// `max_fee = base_fee * FEE_BUFFER + priority_fee_at_percentile`, so a fee
+/// spike between submission and inclusion doesn't strand the transaction.
+const FEE_BUFFER: u128 = 2;
+
+/// How many consecutive failures a provider can rack up before we evict it
+/// from the healthy rotation.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+/// How long an evicted provider sits out before we give it another chance.
+const EVICTION_COOLDOWN: Duration = Duration::from_secs(30);
+/// How many re-dial attempts a reconnect will make before giving up and
+/// telling the affected subscriber/requester to stop waiting.
+const RECONNECT_MAX_ATTEMPTS: u32 = 8;
+/// Base delay for the reconnect backoff; doubled on every failed attempt, up
+/// to a cap of 64x the base.
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(250);
+/// Default number of request tokens a process may burst before it has to
+/// wait on refill, for callers with no granted rate-limit capability.
+const DEFAULT_RATE_LIMIT_CAPACITY: f64 = 100.0;
+/// Default steady-state rate new tokens trickle back in, once a process has
+/// burned through its burst capacity.
+const DEFAULT_RATE_LIMIT_REFILL_PER_SEC: f64 = 20.0;
+/// Token cost charged against a process's bucket for opening a log
+/// subscription (`EthAction::SubscribeLogs`), on top of the ongoing
+/// per-message cost charged for every delivered log for as long as the
+/// subscription stays open. Block subscriptions (`EthAction::SubscribeBlocks`)
+/// are not charged, neither on opening nor per delivered head.
+const SUBSCRIPTION_TOKEN_COST: f64 = 10.0;
+
+/// A record of an active subscription's original request, kept around so we
+/// can replay the `eth_subscribe` call against a freshly re-dialed
+/// connection after the backend drops out from under us.
+type SubscriptionRegistry = DashMap<(ProcessId, u64), (serde_json::Value, serde_json::Value, Address)>;
+/// Handles of long-running subscription stream tasks, keyed the same way as
+/// [`SubscriptionRegistry`] so the two stay in sync.
+type ConnectionMap = DashMap<(ProcessId, u64), JoinHandle<Result<(), EthError>>>;
+
+/// One configured RPC endpoint: its URL and its priority tier. Lower tier
+/// numbers are tried first; within a tier, providers are tried in the order
+/// they were configured.
+#[derive(Clone, Debug)]
+pub struct RpcEndpoint {
+    pub url: String,
+    pub tier: u8,
+    /// How often a polling-emulated log subscription against this endpoint
+    /// re-queries `eth_getLogs`. Only consulted for `http(s)://` endpoints,
+    /// which have no `eth_subscribe` push channel; `ws(s)://` endpoints
+    /// stream and ignore it. Defaults to `HTTP_POLL_INTERVAL` when unset, so
+    /// operators on a provider with a tighter or looser rate limit can tune
+    /// it per-endpoint instead of needing a recompile.
+    pub poll_interval: Option<Duration>,
+}
+
+/// Tracks whether a given provider is currently considered healthy.
+struct ProviderHealth {
+    consecutive_failures: u32,
+    last_success: Option<Instant>,
+    evicted_until: Option<Instant>,
+}
+
+impl ProviderHealth {
+    fn new() -> Self {
+        Self {
+            consecutive_failures: 0,
+            last_success: None,
+            evicted_until: None,
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        match self.evicted_until {
+            Some(until) => Instant::now() >= until,
+            None => true,
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.evicted_until = None;
+        self.last_success = Some(Instant::now());
+    }
+
+    fn record_failure(&mut self) {
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+            self.evicted_until = Some(Instant::now() + EVICTION_COOLDOWN);
+        }
+    }
+}
+
+/// A connected RPC client, over whichever transport the configured URL asked
+/// for. `Ws` carries a pubsub connection that supports real `eth_subscribe`;
+/// `Http` has no push channel, so subscriptions against it are emulated by
+/// polling (see [`poll_logs_subscription`]).
+#[derive(Clone)]
+enum Backend {
+    Ws(Arc<alloy_providers::provider::Provider<PubSubFrontend>>),
+    Http(Arc<alloy_providers::provider::Provider<Http<reqwest::Client>>>),
+}
+
+impl Backend {
+    async fn prepare(
+        &self,
+        method: &'static str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, EthError> {
+        match self {
+            Backend::Ws(provider) => provider
+                .inner()
+                .prepare(method, params)
+                .await
+                .map_err(|e| classify_rpc_error(method, e)),
+            Backend::Http(provider) => provider
+                .inner()
+                .prepare(method, params)
+                .await
+                .map_err(|e| classify_rpc_error(method, e)),
+        }
+    }
+}
+
+/// Alloy keeps a proper JSON-RPC error response (`ErrorResp`, e.g. `execution
+/// reverted`, bad params, nonce-too-low) distinct from a transport failure
+/// (connection refused, timeout, decode failure). Only the latter says
+/// anything about the backend's health: an application-level error is the
+/// correct, final answer for this request and retrying it against another
+/// provider or reconnecting won't change it.
+fn classify_rpc_error(method: &str, err: RpcError<TransportErrorKind>) -> EthError {
+    match err {
+        RpcError::ErrorResp(payload) => EthError::RpcError(format!(
+            "eth: {} returned an RPC error: {:?}",
+            method, payload
+        )),
+        other => EthError::ProviderError(format!("eth: {} transport error: {:?}", method, other)),
+    }
+}
+
+async fn connect(rpc_url: &str) -> Result<Backend> {
+    match Url::parse(rpc_url)?.scheme() {
+        "ws" | "wss" => {
+            let connector = WsConnect {
+                url: rpc_url.to_string(),
+                auth: None,
+            };
+            let client = ClientBuilder::default().pubsub(connector).await?;
+            Ok(Backend::Ws(Arc::new(
+                alloy_providers::provider::Provider::new_with_client(client),
+            )))
+        }
+        "http" | "https" => {
+            let client = ClientBuilder::default().reqwest_http(Url::parse(rpc_url)?);
+            Ok(Backend::Http(Arc::new(
+                alloy_providers::provider::Provider::new_with_client(client),
+            )))
+        }
+        s => Err(anyhow::anyhow!(
+            "eth: you provided a `{s:?}` Ethereum RPC, but only `ws(s)://` and `http(s)://` are supported. Please try again with a valid provider"
+        )),
+    }
+}
+
+/// Dial `rpc_url` with the same bounded exponential backoff as
+/// [`PooledProvider::reconnect`], so a transient failure at startup doesn't
+/// immediately write that endpoint off.
+async fn connect_with_retry(rpc_url: &str) -> Result<Backend> {
+    let mut attempt: u32 = 0;
+    loop {
+        match connect(rpc_url).await {
+            Ok(backend) => return Ok(backend),
+            Err(e) => {
+                attempt += 1;
+                if attempt >= RECONNECT_MAX_ATTEMPTS {
+                    return Err(e).context(format!(
+                        "eth: gave up connecting to {} after {} attempts",
+                        rpc_url, attempt
+                    ));
+                }
+                let delay = RECONNECT_BASE_DELAY * 2u32.pow(attempt.min(6));
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// A single connected backend, plus the bookkeeping needed to route around it
+/// when it's unwell. The client itself sits behind a `RwLock` so a
+/// reconnect can swap in a fresh connection without invalidating the `Arc`
+/// every other task holds onto this backend.
+struct PooledProvider {
+    endpoint: RpcEndpoint,
+    backend: RwLock<Backend>,
+    health: Mutex<ProviderHealth>,
+    reconnecting: AtomicBool,
+}
+
+impl PooledProvider {
+    async fn current(&self) -> Backend {
+        self.backend.read().await.clone()
+    }
+
+    /// Re-dial this backend with exponential backoff, swapping the fresh
+    /// client into place for every future request/subscription as soon as
+    /// it connects. Mirrors ethers-rs's reconnection-and-request-reissuance
+    /// behavior: callers that hit a transport error reconnect here and then
+    /// reissue whatever they were doing against the new client.
+    async fn reconnect(&self) -> Result<Backend, EthError> {
+        let mut attempt: u32 = 0;
+        loop {
+            match connect(&self.endpoint.url).await {
+                Ok(fresh) => {
+                    *self.backend.write().await = fresh.clone();
+                    self.health.lock().await.record_success();
+                    return Ok(fresh);
+                }
+                Err(e) => {
+                    attempt += 1;
+                    if attempt >= RECONNECT_MAX_ATTEMPTS {
+                        self.health.lock().await.record_failure();
+                        return Err(EthError::ProviderError(format!(
+                            "eth: gave up reconnecting to {} after {} attempts: {:?}",
+                            self.endpoint.url, attempt, e
+                        )));
+                    }
+                    let delay = RECONNECT_BASE_DELAY * 2u32.pow(attempt.min(6));
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Kick off a reconnect in the background if one isn't already running
+    /// for this backend. Lets `request_with_failover` move on to the next
+    /// healthy provider immediately instead of blocking on the full
+    /// retry-with-backoff loop, while still making sure concurrent callers
+    /// that all hit the same dead backend share one reconnect attempt
+    /// instead of each launching their own retry storm.
+    fn spawn_reconnect(self: &Arc<Self>) {
+        if self
+            .reconnecting
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return;
+        }
+        let this = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = this.reconnect().await {
+                println!(
+                    "eth: background reconnect to {} failed: {:?}",
+                    this.endpoint.url, e
+                );
+            }
+            this.reconnecting.store(false, Ordering::Release);
+        });
+    }
+}
+
+/// A pool of connected ETH RPC providers, ordered by configured tier. Requests
+/// are routed to the highest-priority healthy provider, falling back to the
+/// next one on transport error, so a single stalled or misbehaving backend
+/// doesn't take down ETH access for every app on the node.
+pub struct ProviderPool {
+    providers: Vec<Arc<PooledProvider>>,
+}
+
+impl ProviderPool {
+    /// Dials every configured endpoint independently and concurrently, so one
+    /// unreachable lower-tier failover URL can't abort startup for the whole
+    /// pool. Each endpoint gets its own bounded retry-with-backoff (the same
+    /// shape as [`PooledProvider::reconnect`]); an endpoint that's still
+    /// unreachable after that is logged and dropped rather than failing the
+    /// whole pool. Only if every single endpoint is unreachable does this
+    /// return `Err`.
+    async fn connect(endpoints: Vec<RpcEndpoint>) -> Result<Self> {
+        let dials = endpoints
+            .into_iter()
+            .map(|endpoint| tokio::spawn(async move {
+                let backend = connect_with_retry(&endpoint.url).await;
+                (endpoint, backend)
+            }))
+            .collect::<Vec<_>>();
+
+        let mut providers = Vec::with_capacity(dials.len());
+        for dial in dials {
+            let (endpoint, backend) = dial.await?;
+            match backend {
+                Ok(backend) => providers.push(Arc::new(PooledProvider {
+                    endpoint,
+                    backend: RwLock::new(backend),
+                    health: Mutex::new(ProviderHealth::new()),
+                    reconnecting: AtomicBool::new(false),
+                })),
+                Err(e) => println!(
+                    "eth: dropping unreachable provider {} from the pool: {:?}",
+                    endpoint.url, e
+                ),
+            }
+        }
+
+        if providers.is_empty() {
+            return Err(anyhow::anyhow!(
+                "eth: failed to connect to any configured RPC provider"
+            ));
+        }
+
+        // stable sort keeps configuration order within a tier.
+        providers.sort_by_key(|p| p.endpoint.tier);
+        Ok(Self { providers })
+    }
+
+    /// The providers currently believed to be healthy, highest priority first.
+    /// Falls back to the full (tier-sorted) list if every provider is
+    /// presently evicted, since serving from a recently-failing backend beats
+    /// serving nothing.
+    async fn healthy_providers(&self) -> Vec<Arc<PooledProvider>> {
+        let mut healthy = Vec::with_capacity(self.providers.len());
+        for p in &self.providers {
+            if p.health.lock().await.is_healthy() {
+                healthy.push(p.clone());
+            }
+        }
+        if healthy.is_empty() {
+            self.providers.clone()
+        } else {
+            healthy
+        }
+    }
+
+    /// The single highest-priority healthy provider, used for long-lived
+    /// subscriptions where we can't transparently retry mid-stream.
+    async fn best_provider(&self) -> Result<Arc<PooledProvider>, EthError> {
+        self.healthy_providers()
+            .await
+            .into_iter()
+            .next()
+            .ok_or(EthError::ProviderError(
+                "eth: no configured RPC providers".to_string(),
+            ))
+    }
+}
+
+/// Maintains a single shared view of the chain head for the whole process,
+/// fed by one upstream `newHeads` subscription (or, lacking a websocket
+/// backend, periodic polling) rather than every subscribing app opening its
+/// own. `eth_blockNumber` and `eth_getBlockByNumber("latest")` are served
+/// straight from here instead of hitting the backend.
+struct BlockWatcher {
+    latest: RwLock<Option<(u64, serde_json::Value)>>,
+    subscribers: DashMap<(ProcessId, u64), Address>,
+}
+
+impl BlockWatcher {
+    fn new() -> Self {
+        Self {
+            latest: RwLock::new(None),
+            subscribers: DashMap::new(),
+        }
+    }
+
+    async fn latest_number(&self) -> Option<u64> {
+        self.latest.read().await.as_ref().map(|(n, _)| *n)
+    }
+
+    async fn latest_block(&self) -> Option<serde_json::Value> {
+        self.latest.read().await.as_ref().map(|(_, b)| b.clone())
+    }
+
+    async fn set(&self, number: u64, block: serde_json::Value) {
+        *self.latest.write().await = Some((number, block));
+    }
+}
+
+/// `true` if an `eth_getBlockByNumber`-style params array tags its block as
+/// `"latest"`, the only tag we can safely serve from the cached head.
+fn is_latest_tag(params: &serde_json::Value) -> bool {
+    params.as_array().and_then(|a| a.first()).and_then(|v| v.as_str()) == Some("latest")
+}
+
+/// Serve a request straight from the block watcher's cache when possible,
+/// so hot, repeatedly-issued reads like `eth_blockNumber` don't round-trip
+/// to the backend at all.
+async fn serve_from_block_cache(
+    method: &str,
+    params: &serde_json::Value,
+    watcher: &BlockWatcher,
+) -> Option<serde_json::Value> {
+    match method {
+        "eth_blockNumber" => watcher
+            .latest_number()
+            .await
+            .map(|n| serde_json::Value::String(format!("0x{:x}", n))),
+        "eth_getBlockByNumber" if is_latest_tag(params) => watcher.latest_block().await,
+        _ => None,
+    }
+}
+
+/// Record a freshly-seen block header in the watcher and fan it out to every
+/// process that's attached via `EthAction::SubscribeBlocks`.
+async fn handle_new_head(
+    our: &str,
+    header: &serde_json::Value,
+    watcher: &BlockWatcher,
+    send_to_loop: &MessageSender,
+    gas_oracle: &GasOracle,
+    pool: &ProviderPool,
+) {
+    let Some(number) = header
+        .get("number")
+        .and_then(|v| v.as_str())
+        .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+    else {
+        return;
+    };
+    watcher.set(number, header.clone()).await;
+    update_gas_oracle(pool, gas_oracle, number).await;
+
+    let Ok(event) = serde_json::from_value::<SubscriptionResult>(header.clone()) else {
+        return;
+    };
+
+    for entry in watcher.subscribers.iter() {
+        let sub_id = entry.key().1;
+        let target = entry.value().clone();
+        let _ = send_to_loop
+            .send(KernelMessage {
+                id: rand::random(),
+                source: Address {
+                    node: our.to_string(),
+                    process: ETH_PROCESS_ID.clone(),
+                },
+                target,
+                rsvp: None,
+                message: Message::Request(Request {
+                    inherit: false,
+                    expects_response: None,
+                    body: serde_json::to_vec(&EthResponse::Sub {
+                        id: sub_id,
+                        result: event.clone(),
+                    })
+                    .unwrap(),
+                    metadata: None,
+                    capabilities: vec![],
+                }),
+                lazy_load_blob: None,
+            })
+            .await;
+    }
+}
+
+/// Keeps the block watcher fed for the lifetime of the process: picks the
+/// best available backend, streams `newHeads` over it if it's a websocket,
+/// or falls back to polling `eth_getBlockByNumber("latest")` if it's HTTP,
+/// and re-selects a backend whenever the current one gives up.
+async fn run_block_watcher(
+    our: Arc<String>,
+    pool: Arc<ProviderPool>,
+    watcher: Arc<BlockWatcher>,
+    send_to_loop: MessageSender,
+    gas_oracle: Arc<GasOracle>,
+) {
+    loop {
+        let Ok(backend) = pool.best_provider().await else {
+            tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+            continue;
+        };
+
+        match backend.current().await {
+            Backend::Ws(provider) => {
+                if let Err(e) = stream_new_heads(
+                    &our,
+                    &backend,
+                    provider,
+                    &watcher,
+                    &send_to_loop,
+                    &gas_oracle,
+                    &pool,
+                )
+                .await
+                {
+                    println!("eth: block watcher stream ended, re-selecting backend: {:?}", e);
+                }
+            }
+            Backend::Http(_) => {
+                poll_new_heads(&our, &backend, &watcher, &send_to_loop, &gas_oracle, &pool).await;
+            }
+        }
+
+        tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+    }
+}
+
+async fn stream_new_heads(
+    our: &str,
+    backend: &Arc<PooledProvider>,
+    provider: Arc<alloy_providers::provider::Provider<PubSubFrontend>>,
+    watcher: &Arc<BlockWatcher>,
+    send_to_loop: &MessageSender,
+    gas_oracle: &GasOracle,
+    pool: &ProviderPool,
+) -> Result<(), EthError> {
+    let id = provider
+        .inner()
+        .prepare("eth_subscribe", [serde_json::json!("newHeads")])
+        .await
+        .map_err(|e| EthError::ProviderError(format!("eth: newHeads subscribe failed: {:?}", e)))?;
+    let mut rx = provider.inner().get_raw_subscription(id).await;
+
+    loop {
+        match rx.recv().await {
+            Ok(value) => {
+                if let Ok(header) = serde_json::from_str::<serde_json::Value>(value.get()) {
+                    handle_new_head(our, &header, watcher, send_to_loop, gas_oracle, pool).await;
+                }
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(e) => {
+                println!("eth: newHeads stream error, reconnecting: {:?}", e);
+                let Backend::Ws(fresh) = backend.reconnect().await? else {
+                    return Err(EthError::ProviderError(
+                        "eth: newHeads backend reconnected as a non-websocket transport"
+                            .to_string(),
+                    ));
+                };
+                let id = fresh
+                    .inner()
+                    .prepare("eth_subscribe", [serde_json::json!("newHeads")])
+                    .await
+                    .map_err(|e| EthError::ProviderError(format!("{:?}", e)))?;
+                rx = fresh.inner().get_raw_subscription(id).await;
+            }
+        }
+    }
+}
+
+async fn poll_new_heads(
+    our: &str,
+    backend: &Arc<PooledProvider>,
+    watcher: &Arc<BlockWatcher>,
+    send_to_loop: &MessageSender,
+    gas_oracle: &GasOracle,
+    pool: &ProviderPool,
+) {
+    loop {
+        tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+        let client = backend.current().await;
+        match client
+            .prepare("eth_getBlockByNumber", serde_json::json!(["latest", false]))
+            .await
+        {
+            Ok(block) if !block.is_null() => {
+                handle_new_head(our, &block, watcher, send_to_loop, gas_oracle, pool).await
+            }
+            Ok(_) => {}
+            Err(e) => println!("eth: block poll failed: {:?}", e),
+        }
+    }
+}
+
+/// A cache sitting in front of `request_with_failover` for the common, hot
+/// read paths that many indexing apps repeatedly issue. `eth_chainId` and
+/// `net_version` are computed once at startup and never touch the backend
+/// again; idempotent by-hash reads like `eth_getBlockByHash` and
+/// `eth_getTransactionReceipt` get a small TTL'd cache keyed on
+/// `(method, params)`, with the TTL configurable per deployment. Anything
+/// tagged `"latest"` bypasses the TTL cache entirely, since that answer is
+/// expected to change block-to-block.
+struct ResponseCache {
+    chain_id: serde_json::Value,
+    net_version: serde_json::Value,
+    ttl: Duration,
+    ttl_entries: DashMap<(&'static str, serde_json::Value), (serde_json::Value, Instant)>,
+    ttl_order: Mutex<VecDeque<(&'static str, serde_json::Value)>>,
+}
+
+impl ResponseCache {
+    async fn new(pool: &ProviderPool, ttl: Duration) -> Result<Self> {
+        let chain_id = request_with_failover(pool, "eth_chainId", serde_json::json!([]))
+            .await
+            .map_err(|e| anyhow::anyhow!("eth: failed to fetch chain_id at startup: {:?}", e))?;
+        let net_version = request_with_failover(pool, "net_version", serde_json::json!([]))
+            .await
+            .map_err(|e| anyhow::anyhow!("eth: failed to fetch net_version at startup: {:?}", e))?;
+        Ok(Self {
+            chain_id,
+            net_version,
+            ttl,
+            ttl_entries: DashMap::new(),
+            ttl_order: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    /// Values that never change for a connected chain, served with no
+    /// network round-trip at all.
+    fn immutable(&self, method: &str) -> Option<serde_json::Value> {
+        match method {
+            "eth_chainId" => Some(self.chain_id.clone()),
+            "net_version" => Some(self.net_version.clone()),
+            _ => None,
+        }
+    }
+
+    /// On a hit, bumps the entry to most-recently-used so real LRU eviction
+    /// in `put` doesn't evict a hot, constantly-read entry ahead of a cold
+    /// one that just happens to have been inserted more recently.
+    async fn get(&self, method: &'static str, params: &serde_json::Value) -> Option<serde_json::Value> {
+        if !CACHEABLE_METHODS.contains(&method) || is_latest_tag(params) {
+            return None;
+        }
+        let key = (method, params.clone());
+        let (value, inserted_at) = self.ttl_entries.get(&key).map(|e| e.value().clone())?;
+        if inserted_at.elapsed() > self.ttl {
+            self.ttl_entries.remove(&key);
+            let mut order = self.ttl_order.lock().await;
+            if let Some(pos) = order.iter().position(|k| k == &key) {
+                order.remove(pos);
+            }
+            return None;
+        }
+
+        let mut order = self.ttl_order.lock().await;
+        if let Some(pos) = order.iter().position(|k| k == &key) {
+            order.remove(pos);
+        }
+        order.push_back(key);
+        Some(value)
+    }
+
+    async fn put(&self, method: &'static str, params: serde_json::Value, value: serde_json::Value) {
+        // A `null` response usually means "not found yet" (a pending tx's
+        // receipt, a block that hasn't landed) rather than a stable fact
+        // about the chain. Caching it for the full TTL would keep serving
+        // a stale not-found answer well after the real data shows up, so
+        // don't cache it at all and let the next call hit the backend.
+        if !CACHEABLE_METHODS.contains(&method) || is_latest_tag(&params) || value.is_null() {
+            return;
+        }
+        let key = (method, params);
+        let is_new = self
+            .ttl_entries
+            .insert(key.clone(), (value, Instant::now()))
+            .is_none();
+        if is_new {
+            let mut order = self.ttl_order.lock().await;
+            order.push_back(key);
+            if order.len() > CACHE_CAPACITY {
+                if let Some(oldest) = order.pop_front() {
+                    self.ttl_entries.remove(&oldest);
+                }
+            }
+        }
+    }
+}
+
+/// How urgently a caller wants their transaction included, mapped to a
+/// priority-fee percentile over the oracle's sample window.
+#[derive(Clone, Copy)]
+enum GasSpeed {
+    Slow,
+    Standard,
+    Fast,
+}
+
+impl GasSpeed {
+    fn from_str(speed: &str) -> Self {
+        match speed {
+            "slow" => GasSpeed::Slow,
+            "fast" => GasSpeed::Fast,
+            _ => GasSpeed::Standard,
+        }
+    }
+
+    fn percentile(self) -> usize {
+        match self {
+            GasSpeed::Slow => 10,
+            GasSpeed::Standard => 50,
+            GasSpeed::Fast => 90,
+        }
+    }
+}
+
+/// This block's base fee, plus the effective priority fee paid by each of
+/// its included transactions (sorted isn't required here; `estimate`
+/// re-sorts the pooled samples).
+struct BlockFeeSample {
+    base_fee: u128,
+    priority_fees: Vec<u128>,
+}
+
+/// A rolling EIP-1559 fee model: keeps a sliding window of recent blocks'
+/// base fees and the priority fees their transactions actually paid, plus
+/// an optional blend of priority fees observed on pending (not-yet-mined)
+/// transactions so estimates can react faster than block cadence.
+/// `eth_feeHistory`-style percentiles are computed over this window to
+/// answer `EthAction::EstimateGas`.
+struct GasOracle {
+    window: RwLock<VecDeque<BlockFeeSample>>,
+    pending_priority_fees: RwLock<VecDeque<u128>>,
+}
+
+impl GasOracle {
+    fn new() -> Self {
+        Self {
+            window: RwLock::new(VecDeque::with_capacity(GAS_ORACLE_WINDOW)),
+            pending_priority_fees: RwLock::new(VecDeque::with_capacity(PENDING_FEE_WINDOW)),
+        }
+    }
+
+    async fn record_block(&self, base_fee: u128, priority_fees: Vec<u128>) {
+        let mut window = self.window.write().await;
+        window.push_back(BlockFeeSample {
+            base_fee,
+            priority_fees,
+        });
+        while window.len() > GAS_ORACLE_WINDOW {
+            window.pop_front();
+        }
+    }
+
+    async fn record_pending_fee(&self, fee: u128) {
+        let mut pending = self.pending_priority_fees.write().await;
+        pending.push_back(fee);
+        while pending.len() > PENDING_FEE_WINDOW {
+            pending.pop_front();
+        }
+    }
+
+    async fn estimate(&self, speed: GasSpeed, pool: &ProviderPool) -> Result<serde_json::Value, EthError> {
+        let base_fee = match self.window.read().await.back() {
+            Some(sample) => sample.base_fee,
+            // window still warming up: nothing to model a percentile from yet.
+            None => return Self::fallback_estimate(pool).await,
+        };
+
+        let mut fees: Vec<u128> = self
+            .window
+            .read()
+            .await
+            .iter()
+            .flat_map(|sample| sample.priority_fees.iter().copied())
+            .collect();
+        fees.extend(self.pending_priority_fees.read().await.iter().copied());
+
+        if fees.is_empty() {
+            return Self::fallback_estimate(pool).await;
+        }
+        fees.sort_unstable();
+        let priority_fee = fees[(fees.len() - 1) * speed.percentile() / 100];
+
+        Ok(fee_estimate_json(base_fee, priority_fee))
+    }
+
+    /// Ask the backend's native fee endpoints directly, for when the sample
+    /// window hasn't seen enough blocks yet to compute a percentile.
+    async fn fallback_estimate(pool: &ProviderPool) -> Result<serde_json::Value, EthError> {
+        let priority_fee = hex_to_u128(
+            &request_with_failover(pool, "eth_maxPriorityFeePerGas", serde_json::json!([])).await?,
+        )
+        .unwrap_or(0);
+        let base_fee =
+            hex_to_u128(&request_with_failover(pool, "eth_gasPrice", serde_json::json!([])).await?)
+                .unwrap_or(0);
+        Ok(fee_estimate_json(base_fee, priority_fee))
+    }
+}
+
+fn fee_estimate_json(base_fee: u128, priority_fee: u128) -> serde_json::Value {
+    let max_fee = base_fee.saturating_mul(FEE_BUFFER).saturating_add(priority_fee);
+    serde_json::json!({
+        "base_fee": format!("0x{:x}", base_fee),
+        "max_priority_fee": format!("0x{:x}", priority_fee),
+        "max_fee": format!("0x{:x}", max_fee),
+    })
+}
+
+fn hex_to_u128(value: &serde_json::Value) -> Option<u128> {
+    let s = value.as_str()?;
+    u128::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+/// The effective priority fee a mined transaction paid: its explicit
+/// `maxPriorityFeePerGas` for EIP-1559 transactions, or `gasPrice - base_fee`
+/// for legacy ones.
+fn effective_priority_fee(tx: &serde_json::Value, base_fee: u128) -> Option<u128> {
+    if let Some(tip) = tx.get("maxPriorityFeePerGas").and_then(hex_to_u128) {
+        return Some(tip);
+    }
+    let gas_price = tx.get("gasPrice").and_then(hex_to_u128)?;
+    Some(gas_price.saturating_sub(base_fee))
+}
+
+/// Fetch the full block (with transactions) for a newly-seen head and fold
+/// its base fee and transactions' priority fees into the gas oracle's
+/// sliding window.
+async fn update_gas_oracle(pool: &ProviderPool, gas_oracle: &GasOracle, number: u64) {
+    let block = match request_with_failover(
+        pool,
+        "eth_getBlockByNumber",
+        serde_json::json!([format!("0x{:x}", number), true]),
+    )
+    .await
+    {
+        Ok(block) => block,
+        Err(e) => {
+            println!("eth: gas oracle failed to fetch block {}: {:?}", number, e);
+            return;
+        }
+    };
+
+    // pre-EIP-1559 chains have no base fee to model a percentile against.
+    let Some(base_fee) = block.get("baseFeePerGas").and_then(hex_to_u128) else {
+        return;
+    };
+    let Some(txs) = block.get("transactions").and_then(|v| v.as_array()) else {
+        return;
+    };
+
+    let priority_fees = txs
+        .iter()
+        .filter_map(|tx| effective_priority_fee(tx, base_fee))
+        .collect();
+
+    gas_oracle.record_block(base_fee, priority_fees).await;
+}
+
+/// Keeps the gas oracle's pending-fee blend fresh by watching the mempool
+/// over a websocket backend, when one is available. Silently idles (falling
+/// back on the block-sampled window and the backend's native fee endpoints)
+/// when every configured provider is HTTP-only.
+async fn run_pending_fee_watcher(pool: Arc<ProviderPool>, gas_oracle: Arc<GasOracle>) {
+    loop {
+        let mut ws = None;
+        for backend in pool.healthy_providers().await {
+            if let Backend::Ws(provider) = backend.current().await {
+                ws = Some((backend, provider));
+                break;
+            }
+        }
+
+        let Some((backend, provider)) = ws else {
+            tokio::time::sleep(BLOCK_POLL_INTERVAL * 5).await;
+            continue;
+        };
+
+        if let Err(e) = stream_pending_fees(&backend, provider, &gas_oracle).await {
+            println!("eth: pending-tx fee watcher ended, retrying: {:?}", e);
+        }
+        tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+    }
+}
+
+async fn stream_pending_fees(
+    backend: &Arc<PooledProvider>,
+    provider: Arc<alloy_providers::provider::Provider<PubSubFrontend>>,
+    gas_oracle: &GasOracle,
+) -> Result<(), EthError> {
+    let id = provider
+        .inner()
+        .prepare(
+            "eth_subscribe",
+            [serde_json::json!("newPendingTransactions")],
+        )
+        .await
+        .map_err(|e| {
+            EthError::ProviderError(format!(
+                "eth: newPendingTransactions subscribe failed: {:?}",
+                e
+            ))
+        })?;
+    let mut rx = provider.inner().get_raw_subscription(id).await;
+
+    loop {
+        match rx.recv().await {
+            Ok(value) => {
+                let Ok(tx_hash) = serde_json::from_str::<serde_json::Value>(value.get()) else {
+                    continue;
+                };
+                let client = backend.current().await;
+                if let Ok(tx) = client
+                    .prepare("eth_getTransactionByHash", serde_json::json!([tx_hash]))
+                    .await
+                {
+                    if let Some(fee) = tx.get("maxPriorityFeePerGas").and_then(hex_to_u128) {
+                        gas_oracle.record_pending_fee(fee).await;
+                    }
+                }
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(e) => return Err(EthError::ProviderError(format!("{:?}", e))),
+        }
+    }
+}
+
+/// A continuously-refilling token bucket, i.e. a GCRA limiter: tokens trickle
+/// back in at `refill_per_sec` up to `capacity`, computed from elapsed wall
+/// time on each check rather than a fixed tick, so idle processes don't need
+/// a background task to stay correct.
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64) -> Self {
+        Self {
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Refill for elapsed time, then try to charge `cost` tokens. On
+    /// insufficient balance, returns how many milliseconds until `cost`
+    /// tokens would be available, for `EthError::RateLimited`.
+    fn try_take(&mut self, capacity: f64, refill_per_sec: f64, cost: f64) -> Result<(), u64> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * refill_per_sec).min(capacity);
+        self.last_refill = now;
+
+        if self.tokens >= cost {
+            self.tokens -= cost;
+            Ok(())
+        } else {
+            let retry_after_ms = ((cost - self.tokens) / refill_per_sec * 1000.0).ceil() as u64;
+            Err(retry_after_ms)
+        }
+    }
+}
+
+/// Per-process token-bucket rate limiting sitting in front of upstream
+/// dispatch, so one noisy or misconfigured process can't flood the shared
+/// provider pool and starve every other caller. Every `ProcessId` gets
+/// `DEFAULT_RATE_LIMIT_CAPACITY`/`DEFAULT_RATE_LIMIT_REFILL_PER_SEC` unless
+/// it presents a capability this process issued raising its budget, so a
+/// trusted indexer can be granted more headroom through the ordinary kernel
+/// capability system rather than a config file.
+struct RateLimiter {
+    buckets: DashMap<ProcessId, TokenBucket>,
+}
+
+impl RateLimiter {
+    fn new() -> Self {
+        Self {
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// Charge `cost` tokens against `process`'s bucket. `cost` is 1 for a
+    /// plain request or a single delivered subscription message, and
+    /// `SUBSCRIPTION_TOKEN_COST` for opening a subscription in the first
+    /// place.
+    fn check(
+        &self,
+        process: &ProcessId,
+        capabilities: &[Capability],
+        cost: f64,
+    ) -> Result<(), EthError> {
+        let (capacity, refill_per_sec) = granted_rate_limit(capabilities)
+            .unwrap_or((DEFAULT_RATE_LIMIT_CAPACITY, DEFAULT_RATE_LIMIT_REFILL_PER_SEC));
+
+        self.buckets
+            .entry(process.clone())
+            .or_insert_with(|| TokenBucket::new(capacity))
+            .try_take(capacity, refill_per_sec, cost)
+            .map_err(|retry_after_ms| EthError::RateLimited { retry_after_ms })
+    }
+}
+
+/// Look for a capability this process itself issued raising the caller's
+/// rate-limit budget above the default, encoded as
+/// `{"rate_limit": {"capacity": <f64>, "refill_per_sec": <f64>}}` in the
+/// capability's params. Lets a trusted indexer be granted more bandwidth
+/// through the kernel's capability system instead of a static config.
+fn granted_rate_limit(capabilities: &[Capability]) -> Option<(f64, f64)> {
+    capabilities.iter().find_map(|cap| {
+        if cap.issuer.process != *ETH_PROCESS_ID {
+            return None;
+        }
+        let params: serde_json::Value = serde_json::from_str(&cap.params).ok()?;
+        let rate_limit = params.get("rate_limit")?;
+        Some((
+            rate_limit.get("capacity")?.as_f64()?,
+            rate_limit.get("refill_per_sec")?.as_f64()?,
+        ))
+    })
+}
+
 /// The ETH provider runtime process is responsible for connecting to one or more ETH RPC providers
 /// and using them to service indexing requests from other apps. This could also be done by a wasm
 /// app, but in the future, this process will hopefully expand in scope to perform more complex
 /// indexing and ETH node responsibilities.
 pub async fn provider(
     our: String,
-    rpc_url: String,
+    rpc_urls: Vec<RpcEndpoint>,
     send_to_loop: MessageSender,
     mut recv_in_client: MessageReceiver,
     _print_tx: PrintSender,
+    cache_ttl: Duration,
 ) -> Result<()> {
     let our = Arc::new(our);
-    // for now, we can only handle WebSocket RPC URLs. In the future, we should
-    // be able to handle HTTP too, at least.
-    // todo add http reqwest..
-    match Url::parse(&rpc_url)?.scheme() {
-        "http" | "https" => {
-            return Err(anyhow::anyhow!(
-                "eth: you provided a `http(s)://` Ethereum RPC, but only `ws(s)://` is supported. Please try again with a `ws(s)://` provider"
-            ));
-        }
-        "ws" | "wss" => {}
-        s => {
-            return Err(anyhow::anyhow!(
-                "eth: you provided a `{s:?}` Ethereum RPC, but only `ws(s)://` is supported. Please try again with a `ws(s)://` provider"
-            ));
-        }
-    }
-
-    let connector = WsConnect {
-        url: rpc_url.clone(),
-        auth: None,
-    };
 
-    // note, reqwest::http is an option here, although doesn't implement .get_watcher()
-    // polling should be an option, investigating
-    // let client = ClientBuilder::default().reqwest_http(Url::from_str(&rpc_url)?);
+    let pool = Arc::new(ProviderPool::connect(rpc_urls).await?);
 
-    let client = ClientBuilder::default().pubsub(connector).await?;
+    // handles of longrunning subscriptions, and the original request behind
+    // each one so we can replay it against a reconnected backend.
+    let connections: Arc<ConnectionMap> = Arc::new(DashMap::new());
+    let subscriptions: Arc<SubscriptionRegistry> = Arc::new(DashMap::new());
 
-    let provider = alloy_providers::provider::Provider::new_with_client(client);
+    let watcher = Arc::new(BlockWatcher::new());
+    let gas_oracle = Arc::new(GasOracle::new());
+    tokio::spawn(run_block_watcher(
+        our.clone(),
+        pool.clone(),
+        watcher.clone(),
+        send_to_loop.clone(),
+        gas_oracle.clone(),
+    ));
+    tokio::spawn(run_pending_fee_watcher(pool.clone(), gas_oracle.clone()));
 
-    // handles of longrunning subscriptions.
-    let connections: DashMap<(ProcessId, u64), JoinHandle<Result<(), EthError>>> = DashMap::new();
-
-    let connections = Arc::new(connections);
-    let provider = Arc::new(provider);
+    let cache = Arc::new(ResponseCache::new(&pool, cache_ttl).await?);
+    let rate_limiter = Arc::new(RateLimiter::new());
 
     while let Some(km) = recv_in_client.recv().await {
         // clone Arcs
         let our = our.clone();
         let send_to_loop = send_to_loop.clone();
-        let provider = provider.clone();
+        let pool = pool.clone();
         let connections = connections.clone();
+        let subscriptions = subscriptions.clone();
+        let watcher = watcher.clone();
+        let cache = cache.clone();
+        let gas_oracle = gas_oracle.clone();
+        let rate_limiter = rate_limiter.clone();
 
         tokio::spawn(async move {
             if let Err(e) = handle_request(
                 &our,
                 &km,
                 &send_to_loop,
-                provider.clone(),
-                connections.clone(),
+                pool,
+                connections,
+                subscriptions,
+                watcher,
+                cache,
+                gas_oracle,
+                rate_limiter,
             )
             .await
             {
@@ -86,8 +1099,13 @@ async fn handle_request(
     our: &str,
     km: &KernelMessage,
     send_to_loop: &MessageSender,
-    provider: Arc<alloy_providers::provider::Provider<PubSubFrontend>>,
-    connections: Arc<DashMap<(ProcessId, u64), JoinHandle<Result<(), EthError>>>>,
+    pool: Arc<ProviderPool>,
+    connections: Arc<ConnectionMap>,
+    subscriptions: Arc<SubscriptionRegistry>,
+    watcher: Arc<BlockWatcher>,
+    cache: Arc<ResponseCache>,
+    gas_oracle: Arc<GasOracle>,
+    rate_limiter: Arc<RateLimiter>,
 ) -> Result<(), EthError> {
     let Message::Request(req) = &km.message else {
         return Err(EthError::ProviderError(
@@ -99,58 +1117,132 @@ async fn handle_request(
         EthError::ProviderError(format!("eth: failed to deserialize request: {:?}", e))
     })?;
 
+    // throttle before doing any upstream-facing work; subscriptions are
+    // charged up front since their ongoing message volume never passes back
+    // through this function again. A throttled caller still gets a real
+    // response (EthResponse::Err, not a dropped connection) so it can back
+    // off instead of hanging forever waiting for one.
+    let throttled = match &action {
+        EthAction::Request { .. } => rate_limiter
+            .check(&km.source.process, &req.capabilities, 1.0)
+            .err(),
+        EthAction::SubscribeLogs { .. } => rate_limiter
+            .check(&km.source.process, &req.capabilities, SUBSCRIPTION_TOKEN_COST)
+            .err(),
+        _ => None,
+    };
+
     // we might want some of these in payloads.. sub items?
-    let return_body: EthResponse = match action {
-        EthAction::SubscribeLogs {
-            sub_id,
-            kind,
-            params,
-        } => {
-            let sub_id = (km.target.process.clone(), sub_id);
-
-            let kind = serde_json::to_value(&kind).unwrap();
-            let params = serde_json::to_value(&params).unwrap();
-
-            let id = provider
-                .inner()
-                .prepare("eth_subscribe", [kind, params])
-                .await
-                .unwrap();
+    let return_body: EthResponse = if let Some(error) = throttled {
+        EthResponse::Err { id: km.id, error }
+    } else {
+        match action {
+            EthAction::SubscribeLogs {
+                sub_id,
+                kind,
+                params,
+            } => {
+                let sub_id = (km.target.process.clone(), sub_id);
 
-            let target = km.source.clone(); // rsvp?
+                let kind = serde_json::to_value(&kind).unwrap();
+                let params = serde_json::to_value(&params).unwrap();
 
-            let rx = provider.inner().get_raw_subscription(id).await;
-            let handle = tokio::spawn(handle_subscription_stream(
-                our.to_string(),
-                sub_id.1.clone(),
-                rx,
-                target,
-                send_to_loop.clone(),
-            ));
+                let backend = pool.best_provider().await?;
+                let target = km.source.clone(); // rsvp?
+                subscriptions.insert(sub_id.clone(), (kind.clone(), params.clone(), target.clone()));
 
-            connections.insert(sub_id, handle);
-            EthResponse::Ok
-        }
-        EthAction::UnsubscribeLogs(sub_id) => {
-            let sub_id = (km.target.process.clone(), sub_id);
-            let handle = connections
-                .remove(&sub_id)
-                .ok_or(EthError::SubscriptionNotFound)?;
+                let handle = match backend.current().await {
+                    Backend::Ws(provider) => {
+                        let id = provider
+                            .inner()
+                            .prepare("eth_subscribe", [kind, params])
+                            .await
+                            .map_err(|e| {
+                                EthError::ProviderError(format!("eth: subscribe failed: {:?}", e))
+                            })?;
+                        let rx = provider.inner().get_raw_subscription(id).await;
+                        tokio::spawn(handle_subscription_stream(
+                            our.to_string(),
+                            sub_id.clone(),
+                            backend,
+                            connections.clone(),
+                            subscriptions.clone(),
+                            rx,
+                            target,
+                            send_to_loop.clone(),
+                            rate_limiter.clone(),
+                            km.source.process.clone(),
+                            req.capabilities.clone(),
+                        ))
+                    }
+                    // plain HTTP has no eth_subscribe; fake it by polling
+                    // eth_getLogs on an interval and diffing against what we've
+                    // already delivered.
+                    Backend::Http(_) => tokio::spawn(poll_logs_subscription(
+                        our.to_string(),
+                        sub_id.clone(),
+                        backend,
+                        connections.clone(),
+                        subscriptions.clone(),
+                        params,
+                        target,
+                        send_to_loop.clone(),
+                        watcher.clone(),
+                        rate_limiter.clone(),
+                        km.source.process.clone(),
+                        req.capabilities.clone(),
+                    )),
+                };
 
-            handle.1.abort();
-            EthResponse::Ok
-        }
-        EthAction::Request { method, params } => {
-            let method = to_static_str(&method).ok_or(EthError::ProviderError(format!(
-                "eth: method not found: {}",
-                method
-            )))?;
+                connections.insert(sub_id, handle);
+                EthResponse::Ok
+            }
+            EthAction::SubscribeBlocks { sub_id } => {
+                let sub_key = (km.target.process.clone(), sub_id);
+                watcher.subscribers.insert(sub_key, km.source.clone());
+                EthResponse::Ok
+            }
+            EthAction::UnsubscribeLogs(sub_id) => {
+                let sub_key = (km.target.process.clone(), sub_id);
+                let had_log_sub = match connections.remove(&sub_key) {
+                    Some((_, handle)) => {
+                        subscriptions.remove(&sub_key);
+                        handle.abort();
+                        true
+                    }
+                    None => false,
+                };
+                let had_block_sub = watcher.subscribers.remove(&sub_key).is_some();
 
-            // throw transportErrorKinds straight back to process
-            let response: serde_json::Value =
-                provider.inner().prepare(method, params).await.unwrap();
+                if !had_log_sub && !had_block_sub {
+                    return Err(EthError::SubscriptionNotFound);
+                }
+                EthResponse::Ok
+            }
+            EthAction::Request { method, params } => {
+                let method = to_static_str(&method).ok_or(EthError::ProviderError(format!(
+                    "eth: method not found: {}",
+                    method
+                )))?;
 
-            EthResponse::Request(response)
+                if let Some(value) = cache.immutable(method) {
+                    EthResponse::Request(value)
+                } else if let Some(value) = serve_from_block_cache(method, &params, &watcher).await {
+                    EthResponse::Request(value)
+                } else if let Some(value) = cache.get(method, &params).await {
+                    EthResponse::Request(value)
+                } else {
+                    let value = request_with_failover(&pool, method, params.clone()).await?;
+                    cache.put(method, params, value.clone()).await;
+                    EthResponse::Request(value)
+                }
+            }
+            EthAction::EstimateGas { speed } => {
+                let estimate = gas_oracle
+                    .estimate(GasSpeed::from_str(&speed), &pool)
+                    .await?;
+                EthResponse::Request(estimate)
+            }
         }
     };
 
@@ -187,30 +1279,309 @@ async fn handle_request(
     Ok(())
 }
 
+/// Try `method` against each healthy provider in priority order, recording
+/// success/failure against the pool's health tracking as we go. Only a
+/// transport-level failure advances to the next provider; an RPC-level error
+/// response is returned immediately since retrying it elsewhere won't help.
+///
+/// A transport failure against a given backend starts a reconnect of that
+/// backend in the background and the request falls through to the next one
+/// in the pool. The failed backend is not retried for this request, so if it
+/// was the last candidate the call fails.
+async fn request_with_failover(
+    pool: &ProviderPool,
+    method: &'static str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, EthError> {
+    let candidates = pool.healthy_providers().await;
+    let mut last_err = None;
+
+    for backend in candidates {
+        let client = backend.current().await;
+        match client.prepare(method, params.clone()).await {
+            Ok(response) => {
+                backend.health.lock().await.record_success();
+                return Ok(response);
+            }
+            // the backend answered just fine; it's just telling us the call
+            // itself is bad (revert, bad params, ...). Nothing about this
+            // provider's health changed, and no other provider will answer
+            // differently, so hand the error straight back to the caller.
+            Err(e @ EthError::RpcError(_)) => return Err(e),
+            Err(e) => {
+                backend.health.lock().await.record_failure();
+                println!(
+                    "eth: provider {} failed, reconnecting in background: {:?}",
+                    backend.endpoint.url, e
+                );
+                // Don't block failover on this backend's full retry-with-backoff
+                // loop (up to ~47s) - kick the reconnect off in the background,
+                // same as run_block_watcher/stream_new_heads do for long-lived
+                // connections, and move on to the next healthy provider now.
+                backend.spawn_reconnect();
+                last_err = Some(e);
+            }
+        }
+    }
+
+    Err(EthError::ProviderError(format!(
+        "eth: all providers failed for {}: {:?}",
+        method, last_err
+    )))
+}
+
 /// Executed as a long-lived task. The JoinHandle is stored in the `connections` map.
 /// This task is responsible for connecting to the ETH RPC provider and streaming logs
-/// for a specific subscription made by a process.
+/// for a specific subscription made by a process, for as long as that subscription
+/// lives: it only returns on a genuine stream close (after reconnection is exhausted),
+/// when the kernel's message loop stops accepting messages, or when
+/// `EthAction::UnsubscribeLogs` aborts it from outside.
 async fn handle_subscription_stream(
     our: String,
-    sub_id: u64,
+    sub_key: (ProcessId, u64),
+    backend: Arc<PooledProvider>,
+    connections: Arc<ConnectionMap>,
+    subscriptions: Arc<SubscriptionRegistry>,
     mut rx: RawSubscription,
     target: Address,
     send_to_loop: MessageSender,
+    rate_limiter: Arc<RateLimiter>,
+    process: ProcessId,
+    capabilities: Vec<Capability>,
 ) -> Result<(), EthError> {
-    match rx.recv().await {
-        Err(e) => {
-            println!("got an error from the subscription stream: {:?}", e);
-            // TODO should we stop the subscription here?
-            // return Err(EthError::ProviderError(format!("{:?}", e)));
-        }
-        Ok(value) => {
-            let event: SubscriptionResult = serde_json::from_str(value.get())
-                .map_err(|e| EthError::ProviderError(format!("{:?}", e)))?;
-            send_to_loop
+    loop {
+        match rx.recv().await {
+            Ok(value) => {
+                let event: SubscriptionResult = match serde_json::from_str(value.get()) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        // one undecodable payload shouldn't kill an otherwise
+                        // healthy long-lived feed; skip it and keep streaming.
+                        println!("eth: dropping undecodable subscription event: {:?}", e);
+                        continue;
+                    }
+                };
+
+                // the flat SUBSCRIPTION_TOKEN_COST at subscribe time only
+                // covers opening the feed; keep charging the same bucket per
+                // delivered message so a subscription with a high match rate
+                // can't flood a downstream process for free. A throttled
+                // message is dropped rather than killing the subscription -
+                // the next poll/event can still land once the bucket refills.
+                if rate_limiter
+                    .check(&process, &capabilities, 1.0)
+                    .is_err()
+                {
+                    continue;
+                }
+
+                let sent = send_to_loop
+                    .send(KernelMessage {
+                        id: rand::random(),
+                        source: Address {
+                            node: our.clone(),
+                            process: ETH_PROCESS_ID.clone(),
+                        },
+                        target: target.clone(),
+                        rsvp: None,
+                        message: Message::Request(Request {
+                            inherit: false,
+                            expects_response: None,
+                            body: serde_json::to_vec(&EthResponse::Sub {
+                                id: sub_key.1,
+                                result: event,
+                            })
+                            .unwrap(),
+                            metadata: None,
+                            capabilities: vec![],
+                        }),
+                        lazy_load_blob: None,
+                    })
+                    .await;
+                if sent.is_err() {
+                    // the kernel's message loop is gone; nothing left to stream to.
+                    connections.remove(&sub_key);
+                    subscriptions.remove(&sub_key);
+                    return Err(EthError::SubscriptionClosed);
+                }
+            }
+            // a lagged receiver missed some events but the connection itself
+            // is fine; that's transient, so just keep going.
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                println!(
+                    "eth: subscription {:?} lagged, skipped {} events",
+                    sub_key, skipped
+                );
+                continue;
+            }
+            // the upstream connection actually went away: hand off to the
+            // reconnection path rather than treating this as end-of-stream.
+            Err(e) => {
+                println!("got an error from the subscription stream: {:?}", e);
+                match resubscribe(&backend, &sub_key, &subscriptions).await {
+                    Ok(new_rx) => {
+                        rx = new_rx;
+                        continue;
+                    }
+                    Err(give_up) => {
+                        println!(
+                            "eth: subscription {:?} could not be recovered, notifying subscriber",
+                            sub_key
+                        );
+                        notify_subscriber(&our, sub_key.1, &target, &send_to_loop, give_up).await;
+                        connections.remove(&sub_key);
+                        subscriptions.remove(&sub_key);
+                        return Err(EthError::SubscriptionClosed);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Re-dial `backend` and replay the original `eth_subscribe` call recorded
+/// for `sub_key`, so a dropped connection can resume streaming to the same
+/// subscriber without it ever having to re-issue `EthAction::SubscribeLogs`.
+async fn resubscribe(
+    backend: &Arc<PooledProvider>,
+    sub_key: &(ProcessId, u64),
+    subscriptions: &SubscriptionRegistry,
+) -> Result<RawSubscription, EthError> {
+    let (kind, params, _target) = subscriptions
+        .get(sub_key)
+        .map(|entry| entry.value().clone())
+        .ok_or(EthError::SubscriptionNotFound)?;
+
+    let Backend::Ws(provider) = backend.reconnect().await? else {
+        return Err(EthError::ProviderError(
+            "eth: cannot replay a log subscription over a non-websocket backend".to_string(),
+        ));
+    };
+
+    let id = provider
+        .inner()
+        .prepare("eth_subscribe", [kind, params])
+        .await
+        .map_err(|e| EthError::ProviderError(format!("eth: re-subscribe failed: {:?}", e)))?;
+
+    Ok(provider.inner().get_raw_subscription(id).await)
+}
+
+/// Emulates a log subscription over plain HTTP, where there's no
+/// `eth_subscribe` push channel to rely on: polls `eth_getLogs` on an
+/// interval, advancing `fromBlock` to the chain head on every poll (whether
+/// or not it produced any matches) so the queried range can't grow without
+/// bound against a narrow filter that simply has nothing to report for a
+/// while. Gives up and notifies the subscriber after `MAX_POLL_FAILURES`
+/// consecutive failures, rather than retrying a range the backend may keep
+/// rejecting forever.
+async fn poll_logs_subscription(
+    our: String,
+    sub_key: (ProcessId, u64),
+    backend: Arc<PooledProvider>,
+    connections: Arc<ConnectionMap>,
+    subscriptions: Arc<SubscriptionRegistry>,
+    mut filter: serde_json::Value,
+    target: Address,
+    send_to_loop: MessageSender,
+    watcher: Arc<BlockWatcher>,
+    rate_limiter: Arc<RateLimiter>,
+    process: ProcessId,
+    capabilities: Vec<Capability>,
+) -> Result<(), EthError> {
+    let mut last_block: Option<u64> = None;
+    let mut consecutive_failures: u32 = 0;
+
+    loop {
+        tokio::time::sleep(backend.endpoint.poll_interval.unwrap_or(HTTP_POLL_INTERVAL)).await;
+
+        if let Some(last) = last_block {
+            if let Some(obj) = filter.as_object_mut() {
+                obj.insert(
+                    "fromBlock".to_string(),
+                    serde_json::Value::String(format!("0x{:x}", last + 1)),
+                );
+            }
+        }
+
+        // bound the query range at the chain head we know about, so a
+        // filter with no matches for a while doesn't leave `fromBlock`
+        // trailing further and further behind as new blocks keep arriving.
+        let head = watcher.latest_number().await;
+        if let Some(head) = head {
+            if let Some(obj) = filter.as_object_mut() {
+                obj.insert(
+                    "toBlock".to_string(),
+                    serde_json::Value::String(format!("0x{:x}", head)),
+                );
+            }
+        }
+
+        let client = backend.current().await;
+        let logs = match client.prepare("eth_getLogs", serde_json::json!([filter])).await {
+            Ok(logs) => logs,
+            Err(e) => {
+                consecutive_failures += 1;
+                println!(
+                    "eth: http log poll for {:?} failed ({}/{}): {:?}",
+                    sub_key, consecutive_failures, MAX_POLL_FAILURES, e
+                );
+                if consecutive_failures >= MAX_POLL_FAILURES {
+                    connections.remove(&sub_key);
+                    subscriptions.remove(&sub_key);
+                    notify_subscriber(&our, sub_key.1, &target, &send_to_loop, e).await;
+                    return Err(EthError::SubscriptionClosed);
+                }
+                let _ = backend.reconnect().await;
+                continue;
+            }
+        };
+        consecutive_failures = 0;
+
+        // whether or not this poll found any matches, we successfully
+        // queried up through `head`, so the next poll's `fromBlock` should
+        // start right after it.
+        if let Some(head) = head {
+            last_block = Some(last_block.map_or(head, |cur| cur.max(head)));
+        }
+
+        let Some(entries) = logs.as_array() else {
+            continue;
+        };
+
+        for log in entries {
+            if let Some(block_hex) = log
+                .get("blockNumber")
+                .and_then(|v| v.as_str())
+                .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+            {
+                last_block = Some(last_block.map_or(block_hex, |cur| cur.max(block_hex)));
+            }
+
+            let event: SubscriptionResult = match serde_json::from_value(log.clone()) {
+                Ok(event) => event,
+                Err(e) => {
+                    println!("eth: dropping undecodable polled log: {:?}", e);
+                    continue;
+                }
+            };
+
+            // same per-message accounting as the websocket path: the
+            // subscribe-time SUBSCRIPTION_TOKEN_COST only pays for opening
+            // the feed, so keep charging per delivered match and drop this
+            // one if the bucket's run dry rather than killing the poll.
+            if rate_limiter
+                .check(&process, &capabilities, 1.0)
+                .is_err()
+            {
+                continue;
+            }
+
+            let sent = send_to_loop
                 .send(KernelMessage {
                     id: rand::random(),
                     source: Address {
-                        node: our,
+                        node: our.clone(),
                         process: ETH_PROCESS_ID.clone(),
                     },
                     target: target.clone(),
@@ -219,7 +1590,7 @@ async fn handle_subscription_stream(
                         inherit: false,
                         expects_response: None,
                         body: serde_json::to_vec(&EthResponse::Sub {
-                            id: sub_id,
+                            id: sub_key.1,
                             result: event,
                         })
                         .unwrap(),
@@ -228,9 +1599,47 @@ async fn handle_subscription_stream(
                     }),
                     lazy_load_blob: None,
                 })
-                .await
-                .unwrap();
+                .await;
+            if sent.is_err() {
+                connections.remove(&sub_key);
+                subscriptions.remove(&sub_key);
+                return Err(EthError::SubscriptionClosed);
+            }
         }
     }
-    Err(EthError::SubscriptionClosed)
+}
+
+/// Push an out-of-band error to a subscriber whose subscription could not be
+/// recovered, so it can react instead of waiting forever for events that
+/// will never arrive.
+async fn notify_subscriber(
+    our: &str,
+    sub_id: u64,
+    target: &Address,
+    send_to_loop: &MessageSender,
+    error: EthError,
+) {
+    let _ = send_to_loop
+        .send(KernelMessage {
+            id: rand::random(),
+            source: Address {
+                node: our.to_string(),
+                process: ETH_PROCESS_ID.clone(),
+            },
+            target: target.clone(),
+            rsvp: None,
+            message: Message::Request(Request {
+                inherit: false,
+                expects_response: None,
+                body: serde_json::to_vec(&EthResponse::Err {
+                    id: sub_id,
+                    error,
+                })
+                .unwrap(),
+                metadata: None,
+                capabilities: vec![],
+            }),
+            lazy_load_blob: None,
+        })
+        .await;
 }